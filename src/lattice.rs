@@ -0,0 +1,204 @@
+use std::collections::VecDeque;
+
+// Which lattice geometry `Field::create` should build. The coordination
+// number (`degree`) varies by geometry, which is what the rest of the
+// simulation (the Boltzmann table, the local-field sum, ...) needs to know
+// about each site's neighborhood.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Geometry {
+    Square2D,
+    Triangular2D,
+    Honeycomb2D,
+    Cubic3D,
+}
+
+impl Geometry {
+    pub fn degree(&self) -> usize {
+        match *self {
+            Geometry::Square2D     => 4,
+            Geometry::Triangular2D => 6,
+            Geometry::Honeycomb2D  => 3,
+            Geometry::Cubic3D      => 6,
+        }
+    }
+
+    // Whether `bipartition`'s 2-coloring is actually race-free for this
+    // geometry, i.e. whether `step_parallel` may be used with it. Only
+    // `Triangular2D` has odd-length cycles (its diagonal bonds close
+    // triangles), so it's the only one that isn't bipartite.
+    pub fn is_bipartite(&self) -> bool {
+        match *self {
+            Geometry::Square2D | Geometry::Honeycomb2D | Geometry::Cubic3D => true,
+            Geometry::Triangular2D => false,
+        }
+    }
+}
+
+// Builds the periodic-boundary adjacency list for `geometry` on a
+// width*height (*depth, for Cubic3D) grid: `adjacency[idx]` is the list of
+// node indices neighboring site `idx`. `depth` is ignored by the 2D
+// geometries.
+pub fn build_adjacency(geometry: Geometry, width: usize, height: usize, depth: usize) -> Vec<Vec<usize>> {
+    match geometry {
+        Geometry::Square2D     => build_square(width, height),
+        Geometry::Triangular2D => build_triangular(width, height),
+        Geometry::Honeycomb2D  => build_honeycomb(width, height),
+        Geometry::Cubic3D      => build_cubic(width, height, depth),
+    }
+}
+
+fn build_square(width: usize, height: usize) -> Vec<Vec<usize>> {
+    let n = width * height;
+    let mut adjacency = Vec::with_capacity(n);
+    for idx in 0..n {
+        let w = idx % width;
+        let h = idx / width;
+        let h_prev = if h == 0          {height-1} else {h-1};
+        let h_next = if h == height - 1 {0}        else {h+1};
+        let w_prev = if w == 0          {width-1}  else {w-1};
+        let w_next = if w == width - 1  {0}        else {w+1};
+        adjacency.push(vec![width * h_prev + w, width * h_next + w,
+                             width * h + w_prev, width * h + w_next]);
+    }
+    adjacency
+}
+
+// The square lattice plus the two diagonal bonds that turn each square cell
+// into a pair of triangles.
+fn build_triangular(width: usize, height: usize) -> Vec<Vec<usize>> {
+    let n = width * height;
+    let mut adjacency = Vec::with_capacity(n);
+    for idx in 0..n {
+        let w = idx % width;
+        let h = idx / width;
+        let h_prev = if h == 0          {height-1} else {h-1};
+        let h_next = if h == height - 1 {0}        else {h+1};
+        let w_prev = if w == 0          {width-1}  else {w-1};
+        let w_next = if w == width - 1  {0}        else {w+1};
+        adjacency.push(vec![width * h_prev + w,      width * h_next + w,
+                             width * h + w_prev,      width * h + w_next,
+                             width * h_prev + w_next, width * h_next + w_prev]);
+    }
+    adjacency
+}
+
+// Brick-wall mapping of the honeycomb lattice onto a rectangular grid: every
+// site keeps its two horizontal bonds, and the vertical bond alternates
+// between "up" and "down" with the sublattice parity (w + h) % 2, giving
+// each site exactly three neighbors.
+//
+// That alternation has to complete an integer number of full periods
+// around the vertical periodic boundary to stay reciprocal (site A lists B
+// as a neighbor only if B also lists A) -- the same reason an odd cycle
+// isn't bipartite. With an odd `height`, the parity at the wrap seam comes
+// back around shifted instead of flipped, which makes several vertical
+// bonds one-directional. So `height` must be even.
+fn build_honeycomb(width: usize, height: usize) -> Vec<Vec<usize>> {
+    assert!(height.is_multiple_of(2),
+            "Geometry::Honeycomb2D requires an even height ({} is odd): \
+             the brick-wall zigzag bond can't stay reciprocal around an \
+             odd-length periodic boundary", height);
+    let n = width * height;
+    let mut adjacency = Vec::with_capacity(n);
+    for idx in 0..n {
+        let w = idx % width;
+        let h = idx / width;
+        let h_prev = if h == 0          {height-1} else {h-1};
+        let h_next = if h == height - 1 {0}        else {h+1};
+        let w_prev = if w == 0          {width-1}  else {w-1};
+        let w_next = if w == width - 1  {0}        else {w+1};
+        let mut neighbor = vec![width * h + w_prev, width * h + w_next];
+        if (w + h).is_multiple_of(2) {
+            neighbor.push(width * h_next + w);
+        } else {
+            neighbor.push(width * h_prev + w);
+        }
+        adjacency.push(neighbor);
+    }
+    adjacency
+}
+
+fn build_cubic(width: usize, height: usize, depth: usize) -> Vec<Vec<usize>> {
+    let n = width * height * depth;
+    let at = |d: usize, h: usize, w: usize| d * width * height + h * width + w;
+    let mut adjacency = Vec::with_capacity(n);
+    for idx in 0..n {
+        let w = idx % width;
+        let h = (idx / width) % height;
+        let d = idx / (width * height);
+        let h_prev = if h == 0          {height-1} else {h-1};
+        let h_next = if h == height - 1 {0}        else {h+1};
+        let w_prev = if w == 0          {width-1}  else {w-1};
+        let w_next = if w == width - 1  {0}        else {w+1};
+        let d_prev = if d == 0          {depth-1}  else {d-1};
+        let d_next = if d == depth - 1  {0}        else {d+1};
+        adjacency.push(vec![at(d, h_prev, w), at(d, h_next, w),
+                             at(d, h, w_prev), at(d, h, w_next),
+                             at(d_prev, h, w), at(d_next, h, w)]);
+    }
+    adjacency
+}
+
+// Two-colors `adjacency` via breadth-first search so that every edge joins
+// a site in `classes[0]` to one in `classes[1]`. This only makes sense for
+// a bipartite lattice (square, honeycomb, simple-cubic); `step_parallel`
+// relies on that property to update a whole class concurrently. Called on
+// a non-bipartite lattice (e.g. triangular) this still returns a partition,
+// just not one where same-class sites are race-free neighbors of nothing.
+pub fn bipartition(adjacency: &[Vec<usize>]) -> [Vec<usize>; 2] {
+    let n = adjacency.len();
+    let mut color: Vec<Option<u8>> = vec![None; n];
+    let mut classes = [Vec::new(), Vec::new()];
+
+    for start in 0..n {
+        if color[start].is_some() {
+            continue;
+        }
+        color[start] = Some(0);
+        classes[0].push(start);
+        let mut queue = VecDeque::new();
+        queue.push_back(start);
+        while let Some(cur) = queue.pop_front() {
+            let next_color = 1 - color[cur].unwrap();
+            for &nidx in adjacency[cur].iter() {
+                if color[nidx].is_none() {
+                    color[nidx] = Some(next_color);
+                    classes[next_color as usize].push(nidx);
+                    queue.push_back(nidx);
+                }
+            }
+        }
+    }
+    classes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_symmetric(adjacency: &[Vec<usize>]) {
+        for (a, neighbors) in adjacency.iter().enumerate() {
+            for &b in neighbors.iter() {
+                assert!(adjacency[b].contains(&a),
+                        "edge {} -> {} is not reciprocated: {} does not list {} as a neighbor",
+                        a, b, b, a);
+            }
+        }
+    }
+
+    #[test]
+    fn adjacency_is_symmetric_for_every_geometry() {
+        assert_symmetric(&build_adjacency(Geometry::Square2D, 5, 4, 1));
+        assert_symmetric(&build_adjacency(Geometry::Square2D, 4, 5, 1));
+        assert_symmetric(&build_adjacency(Geometry::Triangular2D, 5, 4, 1));
+        assert_symmetric(&build_adjacency(Geometry::Honeycomb2D, 4, 4, 1));
+        assert_symmetric(&build_adjacency(Geometry::Honeycomb2D, 5, 6, 1));
+        assert_symmetric(&build_adjacency(Geometry::Cubic3D, 3, 3, 3));
+    }
+
+    #[test]
+    #[should_panic]
+    fn honeycomb_rejects_odd_height() {
+        build_adjacency(Geometry::Honeycomb2D, 4, 5, 1);
+    }
+}
@@ -0,0 +1,523 @@
+#![cfg_attr(all(test, feature = "bench"), feature(test))]
+
+extern crate rand;
+extern crate rayon;
+use rand::{Rng, SeedableRng};
+use rand::rngs::SmallRng;
+use rayon::prelude::*;
+use std::collections::HashMap;
+use std::fmt;
+
+mod lattice;
+pub use lattice::Geometry;
+
+pub mod output;
+
+#[cfg(feature = "python")]
+mod python;
+
+// +1/-1 integer spin. Keeping the representation an `i8` (rather than a
+// two-variant enum) lets the energy bookkeeping below work in plain integer
+// arithmetic, which is both cheaper and exact enough to use as a hash-table
+// key (see `Hamiltonian::boltzmann_table`).
+#[derive(PartialEq, Clone, Copy)]
+struct Spin(i8);
+
+impl Spin {
+    const UP   : Spin = Spin( 1);
+    const DOWN : Spin = Spin(-1);
+
+    fn value(&self) -> i8 {
+        self.0
+    }
+
+    fn flipped(&self) -> Spin {
+        Spin(-self.0)
+    }
+}
+
+impl fmt::Debug for Spin {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.0 {
+            1  => {write!(f, "Spin::UP")}
+            -1 => {write!(f, "Spin::DOWN")}
+            _  => {unreachable!()}
+        }
+    }
+}
+
+impl fmt::Display for Spin {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.0 > 0 {write!(f, "+")} else {write!(f, " ")}
+    }
+}
+
+// `neighbor` holds the site's *complete* neighbor list, as built by
+// `lattice::build_adjacency` for whatever `Geometry` the `Field` was
+// created with -- its length is the geometry's coordination number (4 for
+// a square lattice, 6 for triangular or cubic, 3 for honeycomb).
+#[derive(Debug)]
+struct Node {
+    state    : Spin,
+    neighbor : Vec<usize>,
+}
+
+impl Node {
+    fn new(sp: Spin, neighbor: Vec<usize>) -> Node {
+        Node{state: sp, neighbor}
+    }
+
+    fn randomize<R: Rng>(&mut self, rng: &mut R) {
+        self.state = if rng.gen::<bool>() {Spin::UP} else {Spin::DOWN};
+    }
+}
+
+#[derive(Debug)]
+pub struct Field {
+    geometry    : Geometry,
+    width       : usize,
+    height      : usize,
+    beta        : f64,
+    nodes       : Vec<Node>,
+    // scratch space for wolff_step, reused across calls to avoid reallocating.
+    in_cluster  : Vec<bool>,
+    cluster_stack : Vec<usize>,
+    // Node indices partitioned into two classes such that every bond joins
+    // a site in `color_classes[0]` to one in `color_classes[1]` (see
+    // `lattice::bipartition`). `step_parallel` relies on this to update a
+    // whole class concurrently without data races -- which only holds when
+    // `geometry.is_bipartite()` (square, honeycomb, simple-cubic; not
+    // triangular).
+    color_classes : [Vec<usize>; 2],
+}
+
+impl Field {
+    // `depth` is only meaningful for `Geometry::Cubic3D`; pass 1 for the 2D
+    // geometries.
+    pub fn create(geometry: Geometry, width: usize, height: usize, depth: usize, beta: f64) -> Field {
+        let adjacency = lattice::build_adjacency(geometry, width, height, depth);
+        let color_classes = lattice::bipartition(&adjacency);
+        let n = adjacency.len();
+
+        let nodes = adjacency.into_iter()
+            .map(|neighbor| Node::new(Spin::UP, neighbor))
+            .collect();
+
+        Field{geometry, width, height, beta, nodes,
+              in_cluster: vec![false; n], cluster_stack: Vec::new(),
+              color_classes}
+    }
+
+    pub fn randomize<R: Rng>(&mut self, rng: &mut R) {
+        for i in 0..self.nodes.len() {
+            self.nodes[i].randomize(rng);
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    // Magnetization M = (#up - #down).
+    pub fn magnetization(&self) -> f64 {
+        let mut m = 0.0;
+        for node in self.nodes.iter() {
+            m += node.state.value() as f64;
+        }
+        m
+    }
+
+    // The current spin configuration as a flat, row-major `+1`/`-1` list
+    // (`width*height*depth` long), e.g. for handing to NumPy from the
+    // `python` bindings.
+    pub fn spins(&self) -> Vec<i8> {
+        self.nodes.iter().map(|node| node.state.value()).collect()
+    }
+
+    pub fn print_console(&self) {
+        let lnwdth = self.width * 2+1;
+        let line = String::from_utf8(vec![45; lnwdth]).unwrap();
+        println!("{}", line);
+
+        for h in 0..self.height {
+            for w in 0..self.width {
+                let idx = h * self.width + w;
+                print!("|{}", self.nodes[idx].state);
+            }
+            println!("|");
+            println!("{}", line);
+        }
+        println!();
+    }
+}
+
+pub struct Hamiltonian {
+    j : f64,
+    h : f64,
+    // Acceptance probability min(1, exp(-beta*denergy)) for a single-spin
+    // flip, keyed by (spin being flipped, integer sum of its neighbors'
+    // spins). Both only take a handful of values -- {+1,-1} and, for a
+    // lattice of coordination number `neighbors`, every other integer in
+    // [-neighbors, neighbors] -- so the whole table is precomputed once
+    // instead of calling `exp` on every flip attempt.
+    boltzmann_table : HashMap<(i8, i32), f64>,
+}
+
+impl Hamiltonian {
+    // `neighbors` is the coordination number of the `Field` this
+    // `Hamiltonian` will be used with, i.e. `geometry.degree()`.
+    pub fn new(j: f64, h: f64, beta: f64, neighbors: i32) -> Hamiltonian {
+        let mut table = HashMap::new();
+        for &s in [1i8, -1i8].iter() {
+            let mut local_sum = -neighbors;
+            while local_sum <= neighbors {
+                let denergy = 2.0 * (s as f64) * (j * (local_sum as f64) + h);
+                table.insert((s, local_sum), (-beta * denergy).exp().min(1.0));
+                local_sum += 2;
+            }
+        }
+        Hamiltonian{j, h, boltzmann_table: table}
+    }
+
+    fn calc_energy_node(&self, idx: usize, field: &Field) -> f64 {
+        let node = &field.nodes[idx];
+        let mut bond_energy = 0.0;
+        for &nidx in node.neighbor.iter() {
+            if node.state == field.nodes[nidx].state {
+                bond_energy -= self.j;
+            }
+        }
+        // `node.neighbor` now holds the complete neighbor list, so every
+        // bond gets counted once from each of its two endpoints; halve it
+        // back to a per-bond contribution.
+        -self.h * (node.state.value() as f64) + bond_energy / 2.0
+    }
+    pub fn calc_energy(&self, field: &Field) -> f64 {
+        let mut energy = 0.0;
+        for i in 0..field.nodes.len() {
+            energy += self.calc_energy_node(i, field);
+        }
+        energy
+    }
+
+    // Current spin and the integer sum of its neighbors' spins -- the two
+    // quantities the flip-acceptance probability of site `idx` depends on.
+    fn spin_and_local_sum(&self, idx: usize, field: &Field) -> (i8, i32) {
+        let node = &field.nodes[idx];
+        let mut local_sum = 0i32;
+        for &nidx in node.neighbor.iter() {
+            local_sum += field.nodes[nidx].state.value() as i32;
+        }
+        (node.state.value(), local_sum)
+    }
+
+    // Acceptance probability for flipping site `idx`, read from
+    // `boltzmann_table`.
+    fn acceptance_probability(&self, idx: usize, field: &Field) -> f64 {
+        self.boltzmann_table[&self.spin_and_local_sum(idx, field)]
+    }
+}
+
+// Thermodynamic observables derived from a `Measurement`'s accumulated time
+// series, normalized per lattice site (`N = width*height`).
+#[derive(Debug)]
+pub struct Observables {
+    pub mean_energy_per_site : f64,
+    pub specific_heat        : f64,
+    pub susceptibility       : f64,
+    pub binder_cumulant      : f64,
+}
+
+impl fmt::Display for Observables {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "<E>/N = {}, C = {}, chi = {}, U = {}",
+               self.mean_energy_per_site, self.specific_heat,
+               self.susceptibility, self.binder_cumulant)
+    }
+}
+
+// Accumulates energy and magnetization over a run and reduces them to the
+// observables that locate the critical point: specific heat C, the
+// susceptibility chi, and the Binder cumulant U (whose crossing for
+// different lattice sizes pins down T_c). The first `burn_in` calls to
+// `record` are discarded so the system can equilibrate before any samples
+// are taken.
+pub struct Measurement {
+    burn_in   : usize,
+    count     : usize,
+    sum_e     : f64,
+    sum_e2    : f64,
+    sum_m_abs : f64,
+    sum_m2    : f64,
+    sum_m4    : f64,
+}
+
+impl Measurement {
+    pub fn new(burn_in: usize) -> Measurement {
+        Measurement{burn_in, count: 0,
+                    sum_e: 0.0, sum_e2: 0.0,
+                    sum_m_abs: 0.0, sum_m2: 0.0, sum_m4: 0.0}
+    }
+
+    pub fn record(&mut self, field: &Field, hamiltonian: &Hamiltonian) {
+        if self.burn_in > 0 {
+            self.burn_in -= 1;
+            return;
+        }
+        let e = hamiltonian.calc_energy(field);
+        let m = field.magnetization();
+        self.sum_e     += e;
+        self.sum_e2    += e * e;
+        self.sum_m_abs += m.abs();
+        self.sum_m2    += m * m;
+        self.sum_m4    += m * m * m * m;
+        self.count     += 1;
+    }
+
+    pub fn finalize(&self, field: &Field) -> Observables {
+        // Total site count, not `width * height`: for `Geometry::Cubic3D`
+        // that would under-count by a factor of `depth`.
+        let n     = field.nodes.len() as f64;
+        let count = self.count as f64;
+        let beta  = field.beta;
+
+        let mean_e     = self.sum_e     / count;
+        let mean_e2    = self.sum_e2    / count;
+        let mean_m_abs = self.sum_m_abs / count;
+        let mean_m2    = self.sum_m2    / count;
+        let mean_m4    = self.sum_m4    / count;
+
+        Observables {
+            mean_energy_per_site: mean_e / n,
+            specific_heat:  beta * beta * (mean_e2 - mean_e * mean_e) / n,
+            // Deliberately chi = beta*(<M^2> - <|M|>^2)/N, not the naive
+            // beta*(<M^2> - <M>^2)/N: below T_c, <M> itself averages out to
+            // ~0 by symmetry (the system spends equal time in either
+            // magnetization sign), which would make chi track <M^2>
+            // directly instead of its fluctuations. <|M|> is the standard
+            // finite-size substitution that keeps chi measuring the actual
+            // spread, and is the intended formula here, not an accidental
+            // departure from it.
+            susceptibility: beta * (mean_m2 - mean_m_abs * mean_m_abs) / n,
+            binder_cumulant: 1.0 - mean_m4 / (3.0 * mean_m2 * mean_m2),
+        }
+    }
+}
+
+pub fn step<R: Rng>(field: &mut Field, hamiltonian: &Hamiltonian, rng: &mut R) {
+    for i in 0..field.nodes.len() {
+        if rng.gen::<f64>() < hamiltonian.acceptance_probability(i, field) {
+            field.nodes[i].state = field.nodes[i].state.flipped();
+        }
+    }
+}
+
+// Same Metropolis dynamics as `step`, but each sweep is split into the two
+// checkerboard color classes computed in `Field::create`: every site in one
+// class only reads neighbors from the other class, so a whole class can be
+// evaluated concurrently with rayon without data races. Each site gets its
+// own `SmallRng`, seeded from `rng` xored with the site index rather than
+// shared per-worker, so the draw applied to a given site -- and thus the
+// whole sweep -- is reproducible from a single seed regardless of how rayon
+// schedules the work.
+//
+// Requires a bipartite `Geometry` (square, honeycomb, simple-cubic): on a
+// non-bipartite one (triangular) same-class sites can be neighbors of each
+// other, so updating a whole class concurrently would read stale state and
+// violate detailed balance. Panics rather than silently producing wrong
+// results; use `step` for a triangular field instead.
+pub fn step_parallel<R: Rng>(field: &mut Field, hamiltonian: &Hamiltonian, rng: &mut R) {
+    assert!(field.geometry.is_bipartite(),
+            "step_parallel requires a bipartite geometry (square, honeycomb, cubic); \
+             {:?} is not -- use step() instead", field.geometry);
+    for color in 0..2 {
+        let seed: u64 = rng.gen();
+        let sites = field.color_classes[color].clone();
+
+        let new_states: Vec<Spin> = sites.par_iter()
+            .map(|&idx| {
+                    let mut site_rng = SmallRng::seed_from_u64(seed ^ idx as u64);
+                    let s = field.nodes[idx].state.value();
+                    let mut local_sum = 0i32;
+                    for &nidx in field.nodes[idx].neighbor.iter() {
+                        local_sum += field.nodes[nidx].state.value() as i32;
+                    }
+                    let denergy = 2.0 * (s as f64) * (hamiltonian.j * (local_sum as f64) + hamiltonian.h);
+                    if denergy <= 0.0 || site_rng.gen::<f64>() < (-field.beta * denergy).exp() {
+                        field.nodes[idx].state.flipped()
+                    } else {
+                        field.nodes[idx].state
+                    }
+                })
+            .collect();
+
+        for (&idx, new_state) in sites.iter().zip(new_states) {
+            field.nodes[idx].state = new_state;
+        }
+    }
+}
+
+// Wolff single-cluster update. Grows one cluster of aligned spins from a
+// random seed site and flips it as a whole, which decorrelates the chain
+// far faster than single-spin Metropolis flips (`step`) near T_c.
+//
+// Only valid for zero external field (hamiltonian.h == 0): the bond
+// probability below comes from the Wolff construction for the pure
+// ferromagnetic coupling and does not account for a field term.
+pub fn wolff_step<R: Rng>(field: &mut Field, hamiltonian: &Hamiltonian, rng: &mut R) {
+    for in_c in field.in_cluster.iter_mut() {
+        *in_c = false;
+    }
+    field.cluster_stack.clear();
+
+    let p_add = 1.0 - (-2.0 * field.beta * hamiltonian.j).exp();
+
+    let seed = rng.gen_range(0, field.nodes.len());
+    let cluster_spin = field.nodes[seed].state;
+    field.in_cluster[seed] = true;
+    field.cluster_stack.push(seed);
+
+    while let Some(idx) = field.cluster_stack.pop() {
+        for i in 0..field.nodes[idx].neighbor.len() {
+            let nidx = field.nodes[idx].neighbor[i];
+            if !field.in_cluster[nidx]
+                && field.nodes[nidx].state == cluster_spin
+                && rng.gen::<f64>() < p_add
+            {
+                field.in_cluster[nidx] = true;
+                field.cluster_stack.push(nidx);
+            }
+        }
+    }
+
+    for i in 0..field.nodes.len() {
+        if field.in_cluster[i] {
+            field.nodes[i].state = field.nodes[i].state.flipped();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // All-up field: every bond is aligned, so each site's energy is exactly
+    // `-j*degree/2 - h`, known analytically rather than derived from the
+    // code under test.
+    #[test]
+    fn calc_energy_of_an_all_up_field_matches_the_analytic_value() {
+        let geometry = Geometry::Square2D;
+        let beta = 1.0;
+        let j = 1.0;
+        let h = 0.0;
+        let field = Field::create(geometry, 4, 4, 1, beta);
+        let hamiltonian = Hamiltonian::new(j, h, beta, geometry.degree() as i32);
+
+        let n = field.nodes.len() as f64;
+        let expected = n * (-j * geometry.degree() as f64 / 2.0 - h);
+        assert_eq!(hamiltonian.calc_energy(&field), expected);
+    }
+
+    // A field with zero fluctuation (every recorded sample identical) has
+    // zero specific heat and susceptibility by construction.
+    #[test]
+    fn measurement_of_a_constant_field_has_zero_fluctuation() {
+        let geometry = Geometry::Square2D;
+        let beta = 1.0;
+        let field = Field::create(geometry, 4, 4, 1, beta);
+        let hamiltonian = Hamiltonian::new(1.0, 0.0, beta, geometry.degree() as i32);
+
+        let mut measurement = Measurement::new(0);
+        for _ in 0..10 {
+            measurement.record(&field, &hamiltonian);
+        }
+        let observables = measurement.finalize(&field);
+        assert_eq!(observables.specific_heat, 0.0);
+        assert_eq!(observables.susceptibility, 0.0);
+        assert_eq!(observables.mean_energy_per_site,
+                   hamiltonian.calc_energy(&field) / field.nodes.len() as f64);
+    }
+
+    // With beta*j large enough that `exp(-2*beta*j)` underflows to exactly
+    // 0.0, the Wolff bond-acceptance probability is exactly 1.0, so the
+    // cluster started from any site of a fully aligned field deterministically
+    // grows to the whole lattice and the flip is exact, not just likely.
+    #[test]
+    fn wolff_step_flips_a_fully_aligned_field_entirely() {
+        let geometry = Geometry::Square2D;
+        let beta = 100.0;
+        let hamiltonian = Hamiltonian::new(1.0, 0.0, beta, geometry.degree() as i32);
+        let mut field = Field::create(geometry, 4, 4, 1, beta);
+        let mut rng = SmallRng::seed_from_u64(0);
+
+        wolff_step(&mut field, &hamiltonian, &mut rng);
+
+        assert_eq!(field.spins(), vec![-1i8; field.nodes.len()]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn step_parallel_rejects_a_non_bipartite_geometry() {
+        let geometry = Geometry::Triangular2D;
+        let beta = 1.0;
+        let hamiltonian = Hamiltonian::new(1.0, 0.0, beta, geometry.degree() as i32);
+        let mut field = Field::create(geometry, 4, 4, 1, beta);
+        let mut rng = SmallRng::seed_from_u64(0);
+
+        step_parallel(&mut field, &hamiltonian, &mut rng);
+    }
+}
+
+// Compares the table-based `Hamiltonian::acceptance_probability` against the
+// per-flip `exp` call it replaced, to confirm the lookup table is actually a
+// speedup. Nightly-only (the `test` crate is unstable); build with
+// `cargo +nightly bench --features bench`.
+#[cfg(all(test, feature = "bench"))]
+mod bench {
+    extern crate test;
+
+    use super::*;
+    use self::test::Bencher;
+
+    // The formula `acceptance_probability`'s table is precomputed from --
+    // recomputed on every call instead of looked up.
+    fn exp_acceptance_probability(hamiltonian: &Hamiltonian, idx: usize, field: &Field, beta: f64) -> f64 {
+        let (s, local_sum) = hamiltonian.spin_and_local_sum(idx, field);
+        let denergy = 2.0 * (s as f64) * (hamiltonian.j * (local_sum as f64) + hamiltonian.h);
+        (-beta * denergy).exp().min(1.0)
+    }
+
+    #[bench]
+    fn bench_acceptance_via_exp(b: &mut Bencher) {
+        let beta = 1.0;
+        let hamiltonian = Hamiltonian::new(1.0, 0.0, beta, Geometry::Square2D.degree() as i32);
+        let mut field = Field::create(Geometry::Square2D, 32, 32, 1, beta);
+        let mut rng = rand::thread_rng();
+        field.randomize(&mut rng);
+
+        b.iter(|| {
+            for i in 0..field.nodes.len() {
+                test::black_box(exp_acceptance_probability(&hamiltonian, i, &field, beta));
+            }
+        });
+    }
+
+    #[bench]
+    fn bench_acceptance_via_table(b: &mut Bencher) {
+        let beta = 1.0;
+        let hamiltonian = Hamiltonian::new(1.0, 0.0, beta, Geometry::Square2D.degree() as i32);
+        let mut field = Field::create(Geometry::Square2D, 32, 32, 1, beta);
+        let mut rng = rand::thread_rng();
+        field.randomize(&mut rng);
+
+        b.iter(|| {
+            for i in 0..field.nodes.len() {
+                test::black_box(hamiltonian.acceptance_probability(i, &field));
+            }
+        });
+    }
+}
@@ -0,0 +1,87 @@
+// Alternatives to `Field::print_console`, which clobbers the terminal once
+// a run produces more than a handful of frames: binary PGM/PPM image
+// snapshots of a `Field`, and a CSV log of per-sweep observables, both
+// written through buffered `io::Write` with `io::Result` propagated to the
+// caller instead of panicking on a failed write.
+
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+
+use super::Field;
+
+// Writes `field` as a binary PGM (P5) image: one grayscale pixel per site,
+// white for spin up and black for spin down. For a `Geometry::Cubic3D`
+// field, the `depth` slabs are stacked vertically (each `width x height`,
+// in the same depth-major order `Field::spins` returns them in) rather than
+// declaring a header that doesn't match the pixel count.
+pub fn write_pgm<W: Write>(field: &Field, writer: &mut W) -> io::Result<()> {
+    let spins = field.spins();
+    let rows = spins.len() / field.width();
+    writeln!(writer, "P5")?;
+    writeln!(writer, "{} {}", field.width(), rows)?;
+    writeln!(writer, "255")?;
+    let pixels: Vec<u8> = spins.into_iter()
+        .map(|s| if s > 0 {255} else {0})
+        .collect();
+    writer.write_all(&pixels)
+}
+
+// Writes `field` as a binary PPM (P6) image: one RGB pixel per site, red for
+// spin up and blue for spin down. See `write_pgm` for how `Cubic3D` depth is
+// handled.
+pub fn write_ppm<W: Write>(field: &Field, writer: &mut W) -> io::Result<()> {
+    let spins = field.spins();
+    let rows = spins.len() / field.width();
+    writeln!(writer, "P6")?;
+    writeln!(writer, "{} {}", field.width(), rows)?;
+    writeln!(writer, "255")?;
+    let mut pixels = Vec::with_capacity(spins.len() * 3);
+    for s in spins {
+        if s > 0 {
+            pixels.extend_from_slice(&[255, 0, 0]);
+        } else {
+            pixels.extend_from_slice(&[0, 0, 255]);
+        }
+    }
+    writer.write_all(&pixels)
+}
+
+// Writes a `field` snapshot to `{prefix}{sweep:06}.pgm` every `every`
+// sweeps (including sweep 0), so a whole quench or temperature scan can be
+// captured as an auto-numbered image sequence for offline visualization.
+pub struct SnapshotWriter {
+    prefix : String,
+    every  : usize,
+}
+
+impl SnapshotWriter {
+    pub fn new(prefix: &str, every: usize) -> SnapshotWriter {
+        SnapshotWriter{prefix: prefix.to_string(), every: every.max(1)}
+    }
+
+    pub fn maybe_write(&self, sweep: usize, field: &Field) -> io::Result<()> {
+        if !sweep.is_multiple_of(self.every) {
+            return Ok(());
+        }
+        let path = format!("{}{:06}.pgm", self.prefix, sweep);
+        let mut writer = BufWriter::new(File::create(path)?);
+        write_pgm(field, &mut writer)
+    }
+}
+
+// Appends per-sweep `sweep,energy,magnetization` rows to a CSV file.
+pub struct CsvLogger {
+    writer : BufWriter<File>,
+}
+
+impl CsvLogger {
+    pub fn create(path: &str) -> io::Result<CsvLogger> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        writeln!(writer, "sweep,energy,magnetization")?;
+        Ok(CsvLogger{writer})
+    }
+
+    pub fn log(&mut self, sweep: usize, energy: f64, magnetization: f64) -> io::Result<()> {
+        writeln!(self.writer, "{},{},{}", sweep, energy, magnetization)
+    }
+}
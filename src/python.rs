@@ -0,0 +1,135 @@
+// Python bindings, built only when the `python` feature is enabled. These
+// wrap the simulation up as a single `Field` object so a script can build a
+// lattice, run sweeps, and pull out spins/observables without dealing with
+// the `Field`/`Hamiltonian`/`Measurement`/rng split the Rust API uses
+// internally.
+
+extern crate pyo3;
+
+use pyo3::prelude::*;
+use pyo3::exceptions::PyValueError;
+use rand::SeedableRng;
+use rand::rngs::SmallRng;
+
+use super::{step, Field, Geometry, Hamiltonian, Measurement, Observables};
+
+fn parse_geometry(name: &str) -> PyResult<Geometry> {
+    match name {
+        "square"     => Ok(Geometry::Square2D),
+        "triangular" => Ok(Geometry::Triangular2D),
+        "honeycomb"  => Ok(Geometry::Honeycomb2D),
+        "cubic"      => Ok(Geometry::Cubic3D),
+        _ => Err(PyValueError::new_err(
+            format!("unknown geometry {:?} (expected one of \
+                     \"square\", \"triangular\", \"honeycomb\", \"cubic\")", name))),
+    }
+}
+
+#[pyclass]
+struct PyField {
+    field       : Field,
+    hamiltonian : Hamiltonian,
+    rng         : SmallRng,
+}
+
+#[pymethods]
+impl PyField {
+    #[new]
+    #[pyo3(signature = (width, height, beta, j, h, seed, depth=1, geometry="square".to_string()))]
+    #[allow(clippy::too_many_arguments)]
+    fn new(width: usize, height: usize, beta: f64, j: f64, h: f64, seed: u64,
+           depth: usize, geometry: String) -> PyResult<Self> {
+        let geometry = parse_geometry(&geometry)?;
+        Ok(PyField {
+            field:       Field::create(geometry, width, height, depth, beta),
+            hamiltonian: Hamiltonian::new(j, h, beta, geometry.degree() as i32),
+            rng:         SmallRng::seed_from_u64(seed),
+        })
+    }
+
+    fn randomize(&mut self) {
+        self.field.randomize(&mut self.rng);
+    }
+
+    // Run `sweeps` Metropolis sweeps.
+    fn run(&mut self, sweeps: usize) {
+        for _ in 0..sweeps {
+            step(&mut self.field, &self.hamiltonian, &mut self.rng);
+        }
+    }
+
+    // Run `sweeps` sweeps, discarding the first `burn_in` from the
+    // averages, and return the resulting observables.
+    fn measure(&mut self, sweeps: usize, burn_in: usize) -> PyObservables {
+        let mut measurement = Measurement::new(burn_in);
+        for _ in 0..sweeps {
+            step(&mut self.field, &self.hamiltonian, &mut self.rng);
+            measurement.record(&self.field, &self.hamiltonian);
+        }
+        PyObservables(measurement.finalize(&self.field))
+    }
+
+    // Run `sweeps` sweeps and return the per-sweep `(energy,
+    // magnetization)` time series, e.g. for driving a temperature scan from
+    // NumPy without summarizing each temperature down to `measure`'s
+    // finalized observables first.
+    fn run_series(&mut self, sweeps: usize) -> (Vec<f64>, Vec<f64>) {
+        let mut energies = Vec::with_capacity(sweeps);
+        let mut magnetizations = Vec::with_capacity(sweeps);
+        for _ in 0..sweeps {
+            step(&mut self.field, &self.hamiltonian, &mut self.rng);
+            energies.push(self.hamiltonian.calc_energy(&self.field));
+            magnetizations.push(self.field.magnetization());
+        }
+        (energies, magnetizations)
+    }
+
+    fn spins(&self) -> Vec<i8> {
+        self.field.spins()
+    }
+
+    fn magnetization(&self) -> f64 {
+        self.field.magnetization()
+    }
+
+    fn energy(&self) -> f64 {
+        self.hamiltonian.calc_energy(&self.field)
+    }
+}
+
+#[pyclass]
+struct PyObservables(Observables);
+
+#[pymethods]
+impl PyObservables {
+    #[getter]
+    fn mean_energy_per_site(&self) -> f64 {
+        self.0.mean_energy_per_site
+    }
+
+    #[getter]
+    fn specific_heat(&self) -> f64 {
+        self.0.specific_heat
+    }
+
+    #[getter]
+    fn susceptibility(&self) -> f64 {
+        self.0.susceptibility
+    }
+
+    #[getter]
+    fn binder_cumulant(&self) -> f64 {
+        self.0.binder_cumulant
+    }
+
+    fn __repr__(&self) -> PyResult<String> {
+        Ok(format!("{}", self.0))
+    }
+}
+
+#[pymodule]
+fn ising(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<PyField>()?;
+    m.add_class::<PyObservables>()?;
+    Ok(())
+}